@@ -1,10 +1,12 @@
 #![allow(unused_imports, dead_code, non_snake_case, clippy::all)]
 
 #![feature(test)]
+#![feature(portable_simd)]
 extern crate test;
 use test::Bencher;
 
 use std::mem::transmute;
+use std::hint::unreachable_unchecked;
 
 
 const N: usize = 1_000_000;
@@ -20,37 +22,288 @@ fn make_vec<T, F: FnMut (usize) -> T> (mut f: F) -> Vec<T> {
 }
 
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Kind {
+  Nil,
+  Number,
+  Userdata,
+  Int32,
+  Bool,
+  // ...
+}
+
+
+trait ValueRepr: Sized {
+  fn from_nil () -> Self;
+  fn from_number (data: f64) -> Self;
+  fn from_userdata (handle: Handle) -> Self;
+
+  fn is_number (&self) -> bool;
+  unsafe fn as_number_unchecked (&self) -> f64;
+
+  fn as_userdata<'a> (&self, arena: &'a mut UserdataArena) -> Option<&'a mut Userdata>;
+  unsafe fn as_userdata_handle_unchecked (&self) -> Handle;
+
+  fn kind (&self) -> Kind;
+}
+
+
+trait ValueStorage<V: ValueRepr> {
+  unsafe fn get_unchecked (&self, i: usize) -> V;
+  unsafe fn set_unchecked (&mut self, i: usize, v: V);
+}
+
+impl<V: ValueRepr + Copy> ValueStorage<V> for [V] {
+  unsafe fn get_unchecked (&self, i: usize) -> V { *<[V]>::get_unchecked(self, i) }
+  unsafe fn set_unchecked (&mut self, i: usize, v: V) { *<[V]>::get_unchecked_mut(self, i) = v }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Userdata {
+  tag: u64,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Handle {
+  index: u32,
+  generation: u32,
+}
+
+impl Handle {
+  const INDEX_BITS: u32 = 24;
+  const INDEX_MASK: u64 = (1 << Self::INDEX_BITS) - 1;
+  const GENERATION_MASK: u32 = (1 << Self::INDEX_BITS) - 1;
+
+  fn to_bits (self) -> u64 {
+    (self.index as u64) | ((self.generation as u64) << Self::INDEX_BITS)
+  }
+
+  fn from_bits (bits: u64) -> Self {
+    Self {
+      index: (bits & Self::INDEX_MASK) as u32,
+      generation: ((bits >> Self::INDEX_BITS) & Self::INDEX_MASK) as u32,
+    }
+  }
+}
+
+
+struct Slot {
+  value: Option<Userdata>,
+  generation: u32,
+}
+
+struct UserdataArena {
+  slots: Vec<Slot>,
+  free: Vec<u32>,
+}
+
+impl UserdataArena {
+  fn new () -> Self { Self { slots: Vec::new(), free: Vec::new() } }
+
+  fn insert (&mut self, value: Userdata) -> Handle {
+    if let Some(index) = self.free.pop() {
+      let slot = &mut self.slots[index as usize];
+      slot.value = Some(value);
+      Handle { index, generation: slot.generation }
+    } else {
+      assert!(self.slots.len() < (1 << Handle::INDEX_BITS), "UserdataArena index overflowed Handle's 24-bit index field");
+      let index = self.slots.len() as u32;
+      self.slots.push(Slot { value: Some(value), generation: 0 });
+      Handle { index, generation: 0 }
+    }
+  }
+
+  fn get_mut (&mut self, handle: Handle) -> Option<&mut Userdata> {
+    self.slots.get_mut(handle.index as usize)
+      .filter(|slot| slot.generation == handle.generation)
+      .and_then(|slot| slot.value.as_mut())
+  }
+
+  fn remove (&mut self, handle: Handle) -> Option<Userdata> {
+    let slot = self.slots.get_mut(handle.index as usize)?;
+    if slot.generation != handle.generation { return None }
+
+    let value = slot.value.take();
+    slot.generation = slot.generation.wrapping_add(1) & Handle::GENERATION_MASK;
+    self.free.push(handle.index);
+    value
+  }
+}
+
+
+#[cfg(test)]
+mod userdata_arena_tests {
+  use super::*;
+
+  #[test]
+  fn handle_bit_roundtrip () {
+    let h = Handle { index: 0xABCDEF, generation: 0x123456 };
+    assert_eq!(Handle::from_bits(h.to_bits()), h);
+    assert!(h.to_bits() <= Handle::INDEX_MASK | (Handle::INDEX_MASK << Handle::INDEX_BITS));
+  }
+
+  #[test]
+  fn stale_handle_is_rejected_after_recycle () {
+    let mut arena = UserdataArena::new();
+    let first = arena.insert(Userdata { tag: 1 });
+
+    assert_eq!(arena.remove(first), Some(Userdata { tag: 1 }));
+
+    let second = arena.insert(Userdata { tag: 2 });
+    assert_eq!(second.index, first.index);
+    assert_ne!(second.generation, first.generation);
+
+    assert!(arena.get_mut(first).is_none());
+    assert_eq!(arena.get_mut(second), Some(&mut Userdata { tag: 2 }));
+  }
+}
+
+
+fn run_add_kernel<V: ValueRepr, X: ValueStorage<V> + ?Sized, Y: ValueStorage<V> + ?Sized, R: ValueStorage<V> + ?Sized> (x: &X, y: &Y, results: &mut R) {
+  for i in 0..N {
+    let (a, b) = unsafe { (x.get_unchecked(i), y.get_unchecked(i)) };
+
+    let r = if a.is_number() && b.is_number() {
+      V::from_number(unsafe { a.as_number_unchecked() + b.as_number_unchecked() })
+    } else {
+      V::from_nil()
+    };
+
+    unsafe { results.set_unchecked(i, r) }
+  }
+}
+
+
+fn run_userdata_churn<V: ValueRepr + Copy> (arena: &mut UserdataArena, values: &mut [V], next_tag: &mut u64) -> u64 {
+  let mut acc = 0u64;
+
+  for v in values.iter_mut() {
+    if let Some(u) = v.as_userdata(arena) {
+      acc = acc.wrapping_add(u.tag);
+    }
+
+    let handle = unsafe { v.as_userdata_handle_unchecked() };
+    arena.remove(handle);
+
+    *next_tag += 1;
+    *v = V::from_userdata(arena.insert(Userdata { tag: *next_tag }));
+  }
+
+  acc
+}
+
+
+#[cfg(test)]
+const NUMBER_TEST_CASES: &[f64] = &[
+  0.0, -0.0, 1.0, -1.0,
+  f64::MIN_POSITIVE, -f64::MIN_POSITIVE,
+  5e-324, -5e-324,
+  f64::MAX, f64::MIN,
+  f64::INFINITY, f64::NEG_INFINITY,
+];
+
+#[cfg(test)]
+fn check_nil_and_number_roundtrip<V: ValueRepr> () {
+  assert!(matches!(V::from_nil().kind(), Kind::Nil));
+
+  for &n in NUMBER_TEST_CASES {
+    let v = V::from_number(n);
+    assert!(v.is_number());
+
+    let back = unsafe { v.as_number_unchecked() };
+    assert_eq!(back, n);
+    assert_eq!(back.is_sign_negative(), n.is_sign_negative());
+  }
+}
+
+
 mod aligned_tagged {
   use super::*;
 
+  #[derive(Clone, Copy)]
   enum Value {
     Nil,
     Number(f64),
-    Userdata(*mut ()),
+    Userdata(Handle),
     // ...
   }
 
+  impl ValueRepr for Value {
+    fn from_nil () -> Self { Value::Nil }
+    fn from_number (data: f64) -> Self { Value::Number(data) }
+    fn from_userdata (handle: Handle) -> Self { Value::Userdata(handle) }
+
+    fn is_number (&self) -> bool { matches!(self, Value::Number(_)) }
+
+    unsafe fn as_number_unchecked (&self) -> f64 {
+      match self {
+        Value::Number(n) => *n,
+        _ => unreachable_unchecked()
+      }
+    }
+
+    fn as_userdata<'a> (&self, arena: &'a mut UserdataArena) -> Option<&'a mut Userdata> {
+      match self {
+        Value::Userdata(h) => arena.get_mut(*h),
+        _ => None,
+      }
+    }
+
+    unsafe fn as_userdata_handle_unchecked (&self) -> Handle {
+      match self {
+        Value::Userdata(h) => *h,
+        _ => unreachable_unchecked(),
+      }
+    }
+
+    fn kind (&self) -> Kind {
+      match self {
+        Value::Nil => Kind::Nil,
+        Value::Number(_) => Kind::Number,
+        Value::Userdata(_) => Kind::Userdata,
+      }
+    }
+  }
+
+  #[cfg_attr(miri, ignore)]
   #[bench]
   fn bench_aligned (bencher: &mut Bencher) {
-    let x = make_vec(|i| if i % X_NIL_RATE == 0 { Value::Nil } else { Value::Number(i as f64 * 1.92) });
-    let y = make_vec(|i| if i % Y_NIL_RATE == 0 { Value::Nil } else { Value::Number(i as f64 * 3.13) });
+    let x = make_vec(|i| if i % X_NIL_RATE == 0 { Value::from_nil() } else { Value::from_number(i as f64 * 1.92) });
+    let y = make_vec(|i| if i % Y_NIL_RATE == 0 { Value::from_nil() } else { Value::from_number(i as f64 * 3.13) });
 
-    let mut results = make_vec(|_| Value::Nil);
+    let mut results = make_vec(|_| Value::from_nil());
 
-    bencher.iter(|| {
-      for i in 0..N {
-        let (a, b, r) = unsafe { (
-          x.get_unchecked(i),
-          y.get_unchecked(i),
-          results.get_unchecked_mut(i)
-        ) };
-        
-        match (a, b) {
-          (&Value::Number(a), &Value::Number(b)) => *r = Value::Number(a + b),
-          _ => *r = Value::Nil
-        }
-      }
-    })
+    bencher.iter(|| run_add_kernel(&x[..], &y[..], &mut results[..]))
+  }
+
+  #[cfg_attr(miri, ignore)]
+  #[bench]
+  fn bench_userdata_churn (bencher: &mut Bencher) {
+    const LIVE: usize = 10_000;
+
+    let mut arena = UserdataArena::new();
+    let mut next_tag = 0u64;
+
+    let mut values: Vec<Value> = (0..LIVE)
+      .map(|i| Value::from_userdata(arena.insert(Userdata { tag: i as u64 })))
+      .collect();
+
+    bencher.iter(|| run_userdata_churn(&mut arena, &mut values[..], &mut next_tag))
+  }
+
+  #[test]
+  fn nil_number_roundtrip () { check_nil_and_number_roundtrip::<Value>() }
+
+  #[test]
+  fn userdata_roundtrip () {
+    let mut arena = UserdataArena::new();
+    let handle = arena.insert(Userdata { tag: 42 });
+
+    let v = Value::from_userdata(handle);
+    assert!(matches!(v, Value::Userdata(_)));
+    assert_eq!(v.as_userdata(&mut arena), Some(&mut Userdata { tag: 42 }));
   }
 }
 
@@ -58,14 +311,16 @@ mod aligned_tagged {
 mod separated_type_info {
   use super::*;
 
+  #[derive(Clone, Copy)]
   union ValueData {
     Nil: (),
     Number: f64,
-    Userdata: *mut (),
+    Userdata: Handle,
     // ...
   }
 
   #[repr(u8)]
+  #[derive(Clone, Copy)]
   enum ValueKind {
     Nil,
     Number,
@@ -73,40 +328,205 @@ mod separated_type_info {
     // ...
   }
 
+  #[derive(Clone, Copy)]
+  struct SeparatedValue {
+    kind: ValueKind,
+    data: ValueData,
+  }
+
+  impl ValueRepr for SeparatedValue {
+    fn from_nil () -> Self { Self { kind: ValueKind::Nil, data: ValueData { Nil: () } } }
+    fn from_number (data: f64) -> Self { Self { kind: ValueKind::Number, data: ValueData { Number: data } } }
+    fn from_userdata (handle: Handle) -> Self { Self { kind: ValueKind::Userdata, data: ValueData { Userdata: handle } } }
+
+    fn is_number (&self) -> bool { matches!(self.kind, ValueKind::Number) }
+
+    unsafe fn as_number_unchecked (&self) -> f64 { self.data.Number }
+
+    fn as_userdata<'a> (&self, arena: &'a mut UserdataArena) -> Option<&'a mut Userdata> {
+      match self.kind {
+        ValueKind::Userdata => arena.get_mut(unsafe { self.data.Userdata }),
+        _ => None,
+      }
+    }
+
+    unsafe fn as_userdata_handle_unchecked (&self) -> Handle { self.data.Userdata }
+
+    fn kind (&self) -> Kind {
+      match self.kind {
+        ValueKind::Nil => Kind::Nil,
+        ValueKind::Number => Kind::Number,
+        ValueKind::Userdata => Kind::Userdata,
+      }
+    }
+  }
+
+  struct Separated<'a> {
+    kinds: &'a mut [ValueKind],
+    data: &'a mut [ValueData],
+  }
+
+  impl<'a> ValueStorage<SeparatedValue> for Separated<'a> {
+    unsafe fn get_unchecked (&self, i: usize) -> SeparatedValue {
+      SeparatedValue { kind: *self.kinds.get_unchecked(i), data: *self.data.get_unchecked(i) }
+    }
+
+    unsafe fn set_unchecked (&mut self, i: usize, v: SeparatedValue) {
+      *self.kinds.get_unchecked_mut(i) = v.kind;
+      *self.data.get_unchecked_mut(i) = v.data;
+    }
+  }
+
+  #[cfg_attr(miri, ignore)]
   #[bench]
   fn bench_separated_type_info (bencher: &mut Bencher) {
-    let x = make_vec(|i| if i % X_NIL_RATE == 0 { ValueData { Nil: () } } else { ValueData { Number: i as f64 * 1.92 } });
+    let mut x = make_vec(|i| if i % X_NIL_RATE == 0 { ValueData { Nil: () } } else { ValueData { Number: i as f64 * 1.92 } });
+    let mut x_ts = make_vec(|i| if i % X_NIL_RATE == 0 { ValueKind::Nil } else { ValueKind::Number });
+
+    let mut y = make_vec(|i| if i % Y_NIL_RATE == 0 { ValueData { Nil: () } } else { ValueData { Number: i as f64 * 3.13 } });
+    let mut y_ts = make_vec(|i| if i % Y_NIL_RATE == 0 { ValueKind::Nil } else { ValueKind::Number });
+
+    let mut results = make_vec(|_| ValueData { Nil: () });
+    let mut result_ts = make_vec(|_| ValueKind::Nil);
+
+    bencher.iter(|| run_add_kernel(
+      &Separated { kinds: &mut x_ts, data: &mut x },
+      &Separated { kinds: &mut y_ts, data: &mut y },
+      &mut Separated { kinds: &mut result_ts, data: &mut results }
+    ))
+  }
+
+  fn run_separated_simd_add (x_ts: &[ValueKind], x: &[ValueData], y_ts: &[ValueKind], y: &[ValueData], result_ts: &mut [ValueKind], results: &mut [ValueData]) {
+    use std::simd::prelude::*;
+    use std::simd::{Simd, Mask};
+
+    const LANES: usize = 8;
+    const NUMBER: u8 = ValueKind::Number as u8;
+    const NIL: u8 = ValueKind::Nil as u8;
+
+    let len = x_ts.len();
+
+    let x_tags = unsafe { std::slice::from_raw_parts(x_ts.as_ptr() as *const u8, len) };
+    let y_tags = unsafe { std::slice::from_raw_parts(y_ts.as_ptr() as *const u8, len) };
+    let x_data = unsafe { std::slice::from_raw_parts(x.as_ptr() as *const f64, len) };
+    let y_data = unsafe { std::slice::from_raw_parts(y.as_ptr() as *const f64, len) };
+
+    let chunks = len / LANES;
+
+    for c in 0..chunks {
+      let base = c * LANES;
+
+      let xt = Simd::<u8, LANES>::from_slice(&x_tags[base..base + LANES]);
+      let yt = Simd::<u8, LANES>::from_slice(&y_tags[base..base + LANES]);
+      let m: Mask<i8, LANES> = xt.simd_eq(Simd::splat(NUMBER)) & yt.simd_eq(Simd::splat(NUMBER));
+
+      let a = Simd::<f64, LANES>::from_slice(&x_data[base..base + LANES]);
+      let b = Simd::<f64, LANES>::from_slice(&y_data[base..base + LANES]);
+      let sum = a + b;
+
+      let m64: Mask<i64, LANES> = m.cast();
+      let result = m64.select(sum, Simd::splat(0.0));
+      let result_tags = m.select(Simd::splat(NUMBER), Simd::splat(NIL));
+
+      unsafe {
+        let result_data = std::slice::from_raw_parts_mut(results.as_mut_ptr() as *mut f64, len);
+        result.copy_to_slice(&mut result_data[base..base + LANES]);
+
+        let result_tag_bytes = std::slice::from_raw_parts_mut(result_ts.as_mut_ptr() as *mut u8, len);
+        result_tags.copy_to_slice(&mut result_tag_bytes[base..base + LANES]);
+      }
+    }
+
+    for i in (chunks * LANES)..len {
+      let (at, bt) = unsafe { (x_ts.get_unchecked(i), y_ts.get_unchecked(i)) };
+
+      match (at, bt) {
+        (ValueKind::Number, ValueKind::Number) => {
+          result_ts[i] = ValueKind::Number;
+          results[i] = ValueData { Number: unsafe { x[i].Number + y[i].Number } };
+        },
+        _ => {
+          result_ts[i] = ValueKind::Nil;
+          results[i] = ValueData { Number: 0.0 };
+        }
+      }
+    }
+  }
+
+  #[cfg_attr(miri, ignore)]
+  #[bench]
+  fn bench_separated_simd (bencher: &mut Bencher) {
+    let x = make_vec(|i| if i % X_NIL_RATE == 0 { ValueData { Number: 0.0 } } else { ValueData { Number: i as f64 * 1.92 } });
     let x_ts = make_vec(|i| if i % X_NIL_RATE == 0 { ValueKind::Nil } else { ValueKind::Number });
 
-    let y = make_vec(|i| if i % Y_NIL_RATE == 0 { ValueData { Nil: () } } else { ValueData { Number: i as f64 * 3.13 } });
+    let y = make_vec(|i| if i % Y_NIL_RATE == 0 { ValueData { Number: 0.0 } } else { ValueData { Number: i as f64 * 3.13 } });
     let y_ts = make_vec(|i| if i % Y_NIL_RATE == 0 { ValueKind::Nil } else { ValueKind::Number });
 
-    let mut results = make_vec(|_| ValueData { Nil: () });
+    let mut results = make_vec(|_| ValueData { Number: 0.0 });
     let mut result_ts = make_vec(|_| ValueKind::Nil);
 
-    bencher.iter(|| {
-      for i in 0..N {
-        let (a, at, b, bt, r, rt) = unsafe { (
-          x.get_unchecked(i),
-          x_ts.get_unchecked(i),
-          y.get_unchecked(i),
-          y_ts.get_unchecked(i),
-          results.get_unchecked_mut(i),
-          result_ts.get_unchecked_mut(i)
-        ) };
-
-        match (at, bt) {
-          (ValueKind::Number, ValueKind::Number) => {
-            *r = ValueData { Number: unsafe { a.Number + b.Number } };
-            *rt = ValueKind::Number;
-          },
-          _ => {
-            *r = ValueData { Nil: () };
-            *rt = ValueKind::Nil;
-          }
+    bencher.iter(|| run_separated_simd_add(&x_ts, &x, &y_ts, &y, &mut result_ts, &mut results))
+  }
+
+  #[test]
+  fn separated_simd_matches_scalar_with_tail () {
+    const LEN: usize = 13;
+
+    let x_ts: Vec<_> = (0..LEN).map(|i| if i % 3 == 0 { ValueKind::Nil } else { ValueKind::Number }).collect();
+    let x: Vec<_> = (0..LEN).map(|i| if i % 3 == 0 { ValueData { Number: 0.0 } } else { ValueData { Number: i as f64 } }).collect();
+
+    let y_ts: Vec<_> = (0..LEN).map(|i| if i % 4 == 0 { ValueKind::Nil } else { ValueKind::Number }).collect();
+    let y: Vec<_> = (0..LEN).map(|i| if i % 4 == 0 { ValueData { Number: 0.0 } } else { ValueData { Number: i as f64 * 2.0 } }).collect();
+
+    let mut result_ts = vec![ValueKind::Nil; LEN];
+    let mut results = vec![ValueData { Number: 0.0 }; LEN];
+
+    run_separated_simd_add(&x_ts, &x, &y_ts, &y, &mut result_ts, &mut results);
+
+    for i in 0..LEN {
+      match (x_ts[i], y_ts[i]) {
+        (ValueKind::Number, ValueKind::Number) => {
+          assert!(matches!(result_ts[i], ValueKind::Number));
+          assert_eq!(unsafe { results[i].Number }, unsafe { x[i].Number + y[i].Number });
         }
+        _ => assert!(matches!(result_ts[i], ValueKind::Nil)),
       }
-    })
+    }
+  }
+
+  #[cfg_attr(miri, ignore)]
+  #[bench]
+  fn bench_userdata_churn (bencher: &mut Bencher) {
+    const LIVE: usize = 10_000;
+
+    let mut arena = UserdataArena::new();
+    let mut next_tag = 0u64;
+
+    let mut values: Vec<SeparatedValue> = (0..LIVE)
+      .map(|i| SeparatedValue::from_userdata(arena.insert(Userdata { tag: i as u64 })))
+      .collect();
+
+    bencher.iter(|| run_userdata_churn(&mut arena, &mut values[..], &mut next_tag))
+  }
+
+  #[test]
+  fn nil_number_roundtrip () { check_nil_and_number_roundtrip::<SeparatedValue>() }
+
+  #[test]
+  fn userdata_roundtrip () {
+    let mut arena = UserdataArena::new();
+    let handle = arena.insert(Userdata { tag: 42 });
+    let v = SeparatedValue::from_userdata(handle);
+
+    assert!(matches!(v.kind, ValueKind::Userdata));
+    assert_eq!(v.as_userdata(&mut arena), Some(&mut Userdata { tag: 42 }));
+  }
+
+  #[test]
+  fn nil_does_not_alias_number_field () {
+    let v = SeparatedValue::from_nil();
+    assert!(!v.is_number());
+    assert!(matches!(v.kind, ValueKind::Nil));
   }
 }
 
@@ -114,12 +534,14 @@ mod separated_type_info {
 mod unaligned_tagged {
   use super::*;
 
+  #[derive(Clone, Copy)]
   struct Value {
     discriminant: ValueKind,
     data: [u8; 8]
   }
 
   #[repr(u8)]
+  #[derive(Clone, Copy)]
   enum ValueKind {
     Nil,
     Number,
@@ -132,11 +554,11 @@ mod unaligned_tagged {
     fn is_number (&self) -> bool { matches!(self.discriminant, ValueKind::Number) }
     fn is_userdata (&self) -> bool { matches!(self.discriminant, ValueKind::Userdata) }
 
-    
+
     unsafe fn as_number_unchecked (&self) -> f64 {
       f64::from_bits(u64::from_ne_bytes(self.data))
     }
-    
+
     fn as_number (&self) -> Option<f64> {
       if self.is_number() {
         Some(unsafe { self.as_number_unchecked() })
@@ -144,33 +566,53 @@ mod unaligned_tagged {
         None
       }
     }
-    
-    unsafe fn as_userdata_unchecked (&self) -> *mut () {
-      transmute(u64::from_ne_bytes(self.data))
-    }
-    
-    fn as_userdata (&self) -> Option<*mut ()> {
-      if self.is_userdata() {
-        Some(unsafe { self.as_userdata_unchecked() })
-      } else {
-        None
-      }
+
+    unsafe fn as_handle_unchecked (&self) -> Handle {
+      Handle::from_bits(u64::from_ne_bytes(self.data))
     }
 
 
     fn from_nil () -> Self {
       Self { discriminant: ValueKind::Nil, data: 0u64.to_ne_bytes() }
     }
-    
+
     fn from_number (data: f64) -> Self {
       Self { discriminant: ValueKind::Number, data: data.to_ne_bytes() }
     }
-    
-    fn from_userdata (data: *mut ()) -> Self {
-      Self { discriminant: ValueKind::Userdata, data: (data as u64).to_ne_bytes() }
+
+    fn from_handle (handle: Handle) -> Self {
+      Self { discriminant: ValueKind::Userdata, data: handle.to_bits().to_ne_bytes() }
     }
   }
 
+  impl ValueRepr for Value {
+    fn from_nil () -> Self { Self::from_nil() }
+    fn from_number (data: f64) -> Self { Self::from_number(data) }
+    fn from_userdata (handle: Handle) -> Self { Self::from_handle(handle) }
+
+    fn is_number (&self) -> bool { Self::is_number(self) }
+    unsafe fn as_number_unchecked (&self) -> f64 { Self::as_number_unchecked(self) }
+
+    fn as_userdata<'a> (&self, arena: &'a mut UserdataArena) -> Option<&'a mut Userdata> {
+      if self.is_userdata() {
+        arena.get_mut(unsafe { self.as_handle_unchecked() })
+      } else {
+        None
+      }
+    }
+
+    unsafe fn as_userdata_handle_unchecked (&self) -> Handle { self.as_handle_unchecked() }
+
+    fn kind (&self) -> Kind {
+      match self.discriminant {
+        ValueKind::Nil => Kind::Nil,
+        ValueKind::Number => Kind::Number,
+        ValueKind::Userdata => Kind::Userdata,
+      }
+    }
+  }
+
+  #[cfg_attr(miri, ignore)]
   #[bench]
   fn bench_unaligned (bencher: &mut Bencher) {
     let x = make_vec(|i| if i % X_NIL_RATE == 0 { Value::from_nil() } else { Value::from_number(i as f64 * 1.92) });
@@ -178,20 +620,35 @@ mod unaligned_tagged {
 
     let mut results = make_vec(|_| Value::from_nil());
 
-    bencher.iter(|| {
-      for i in 0..N {
-        let (a, b, r) = unsafe { (
-          x.get_unchecked(i),
-          y.get_unchecked(i),
-          results.get_unchecked_mut(i)
-        ) };
-
-        match (&a.discriminant, &b.discriminant) {
-          (ValueKind::Number, ValueKind::Number) => *r = Value::from_number(unsafe { a.as_number_unchecked() + b.as_number_unchecked() }),
-          _ => *r = Value::from_nil()
-        }
-      }
-    })
+    bencher.iter(|| run_add_kernel(&x[..], &y[..], &mut results[..]))
+  }
+
+  #[cfg_attr(miri, ignore)]
+  #[bench]
+  fn bench_userdata_churn (bencher: &mut Bencher) {
+    const LIVE: usize = 10_000;
+
+    let mut arena = UserdataArena::new();
+    let mut next_tag = 0u64;
+
+    let mut values: Vec<Value> = (0..LIVE)
+      .map(|i| Value::from_userdata(arena.insert(Userdata { tag: i as u64 })))
+      .collect();
+
+    bencher.iter(|| run_userdata_churn(&mut arena, &mut values[..], &mut next_tag))
+  }
+
+  #[test]
+  fn nil_number_roundtrip () { check_nil_and_number_roundtrip::<Value>() }
+
+  #[test]
+  fn userdata_roundtrip () {
+    let mut arena = UserdataArena::new();
+    let handle = arena.insert(Userdata { tag: 42 });
+    let v = Value::from_userdata(handle);
+
+    assert!(v.is_userdata());
+    assert_eq!(v.as_userdata(&mut arena), Some(&mut Userdata { tag: 42 }));
   }
 }
 
@@ -199,6 +656,7 @@ mod unaligned_tagged {
 mod nan_tagged {
   use super::*;
 
+  #[derive(Clone, Copy)]
   struct Value(u64);
 
   #[repr(u64)]
@@ -206,6 +664,8 @@ mod nan_tagged {
     Number   = 0u64 << 48,
     Nil      = 1u64 << 48,
     Userdata = 2u64 << 48,
+    Int32    = 3u64 << 48,
+    Bool     = 4u64 << 48,
     // ...
   }
 
@@ -239,6 +699,14 @@ mod nan_tagged {
       self.is_nan() & self.compare_type_segment(ValueKind::Userdata)
     }
 
+    fn is_int (&self) -> bool {
+      self.is_nan() & self.compare_type_segment(ValueKind::Int32)
+    }
+
+    fn is_bool (&self) -> bool {
+      self.is_nan() & self.compare_type_segment(ValueKind::Bool)
+    }
+
 
     unsafe fn as_number_unchecked (&self) -> f64 { *(self as *const _ as *const f64) }
 
@@ -250,22 +718,71 @@ mod nan_tagged {
       }
     }
 
-    unsafe fn as_userdata_unchecked (&self) -> *mut () { self.get_data_segment() as _ }
+    unsafe fn as_handle_unchecked (&self) -> Handle { Handle::from_bits(self.get_data_segment()) }
 
-    fn as_userdata (&self) -> Option<*mut ()> {
-      if self.is_userdata() {
-        Some(unsafe { self.as_userdata_unchecked() })
+    unsafe fn as_int_unchecked (&self) -> i32 { self.get_data_segment() as u32 as i32 }
+
+    fn as_int (&self) -> Option<i32> {
+      if self.is_int() {
+        Some(unsafe { self.as_int_unchecked() })
       } else {
         None
       }
     }
 
+    unsafe fn as_bool_unchecked (&self) -> bool { self.get_data_segment() != 0 }
+
+    fn as_bool (&self) -> Option<bool> {
+      if self.is_bool() {
+        Some(unsafe { self.as_bool_unchecked() })
+      } else {
+        None
+      }
+    }
+
+
+    fn from_number (data: f64) -> Self {
+      if data.is_nan() {
+        Self(Self::NAN_MASK | ValueKind::Number as u64)
+      } else {
+        unsafe { transmute(data) }
+      }
+    }
 
-    fn from_number (data: f64) -> Self { unsafe { transmute(data) } }
     fn from_nil () -> Self { Self(Self::NAN_MASK | ValueKind::Nil as u64) }
-    fn from_userdata (data: *mut ()) -> Self { Self(data as u64 | Self::NAN_MASK | ValueKind::Userdata as u64) }
+    fn from_handle (handle: Handle) -> Self { Self(Self::NAN_MASK | ValueKind::Userdata as u64 | handle.to_bits()) }
+    fn from_int (data: i32) -> Self { Self(Self::NAN_MASK | ValueKind::Int32 as u64 | (data as u32 as u64)) }
+    fn from_bool (data: bool) -> Self { Self(Self::NAN_MASK | ValueKind::Bool as u64 | data as u64) }
   }
 
+  impl ValueRepr for Value {
+    fn from_nil () -> Self { Self::from_nil() }
+    fn from_number (data: f64) -> Self { Self::from_number(data) }
+    fn from_userdata (handle: Handle) -> Self { Self::from_handle(handle) }
+
+    fn is_number (&self) -> bool { Self::is_number(self) }
+    unsafe fn as_number_unchecked (&self) -> f64 { Self::as_number_unchecked(self) }
+
+    fn as_userdata<'a> (&self, arena: &'a mut UserdataArena) -> Option<&'a mut Userdata> {
+      if self.is_userdata() {
+        arena.get_mut(unsafe { self.as_handle_unchecked() })
+      } else {
+        None
+      }
+    }
+
+    unsafe fn as_userdata_handle_unchecked (&self) -> Handle { self.as_handle_unchecked() }
+
+    fn kind (&self) -> Kind {
+      if self.is_number() { Kind::Number }
+      else if self.is_nil() { Kind::Nil }
+      else if self.is_int() { Kind::Int32 }
+      else if self.is_bool() { Kind::Bool }
+      else { Kind::Userdata }
+    }
+  }
+
+  #[cfg_attr(miri, ignore)]
   #[bench]
   fn bench_nan_tagged (bencher: &mut Bencher) {
     let x = make_vec(|i| if i % X_NIL_RATE == 0 { Value::from_nil() } else { Value::from_number(i as f64 * 1.92) });
@@ -273,19 +790,97 @@ mod nan_tagged {
 
     let mut results = make_vec(|_| Value::from_nil());
 
+    bencher.iter(|| run_add_kernel(&x[..], &y[..], &mut results[..]))
+  }
+
+  #[cfg_attr(miri, ignore)]
+  #[bench]
+  fn bench_nan_mixed (bencher: &mut Bencher) {
+    fn as_f64 (v: &Value) -> Option<f64> {
+      if v.is_number() {
+        Some(unsafe { v.as_number_unchecked() })
+      } else if v.is_int() {
+        Some(unsafe { v.as_int_unchecked() } as f64)
+      } else {
+        None
+      }
+    }
+
+    let x = make_vec(|i| if i % 3 == 0 { Value::from_int(i as i32) } else { Value::from_number(i as f64 * 1.92) });
+    let y = make_vec(|i| if i % 5 == 0 { Value::from_int(i as i32 * 2) } else { Value::from_number(i as f64 * 3.13) });
+
+    let mut results = make_vec(|_| Value::from_nil());
+
     bencher.iter(|| {
       for i in 0..N {
-        let (a, b, r) = unsafe { (
-          x.get_unchecked(i),
-          y.get_unchecked(i),
-          results.get_unchecked_mut(i)
-        ) };
-
-        match (a.is_number(), b.is_number()) {
-          (true, true) => *r = Value::from_number(unsafe { a.as_number_unchecked() + b.as_number_unchecked() }),
-          _ => *r = Value::from_nil()
-        }
+        let (a, b) = unsafe { (x.get_unchecked(i), y.get_unchecked(i)) };
+
+        results[i] = match (as_f64(a), as_f64(b)) {
+          (Some(av), Some(bv)) => Value::from_number(av + bv),
+          _ => Value::from_nil(),
+        };
       }
     })
   }
-}
\ No newline at end of file
+
+  #[cfg_attr(miri, ignore)]
+  #[bench]
+  fn bench_userdata_churn (bencher: &mut Bencher) {
+    const LIVE: usize = 10_000;
+
+    let mut arena = UserdataArena::new();
+    let mut next_tag = 0u64;
+
+    let mut values: Vec<Value> = (0..LIVE)
+      .map(|i| Value::from_userdata(arena.insert(Userdata { tag: i as u64 })))
+      .collect();
+
+    bencher.iter(|| run_userdata_churn(&mut arena, &mut values[..], &mut next_tag))
+  }
+
+  #[test]
+  fn nil_number_roundtrip () { check_nil_and_number_roundtrip::<Value>() }
+
+  #[test]
+  fn userdata_roundtrip () {
+    let mut arena = UserdataArena::new();
+    let handle = arena.insert(Userdata { tag: 42 });
+    let v = Value::from_userdata(handle);
+
+    assert!(v.is_userdata());
+    assert_eq!(v.as_userdata(&mut arena), Some(&mut Userdata { tag: 42 }));
+  }
+
+  #[test]
+  fn int_roundtrip () {
+    for i in [0i32, 1, -1, 42, i32::MIN, i32::MAX] {
+      let v = Value::from_int(i);
+
+      assert!(v.is_int());
+      assert_eq!(unsafe { v.as_int_unchecked() }, i);
+      assert_eq!(v.as_int(), Some(i));
+    }
+  }
+
+  #[test]
+  fn bool_roundtrip () {
+    for b in [true, false] {
+      let v = Value::from_bool(b);
+
+      assert!(v.is_bool());
+      assert_eq!(unsafe { v.as_bool_unchecked() }, b);
+      assert_eq!(v.as_bool(), Some(b));
+    }
+  }
+
+  #[test]
+  fn nan_canonicalizes_to_number_tag () {
+    for n in [f64::NAN, -f64::NAN, f64::from_bits(0x7ff8_0000_dead_beef)] {
+      let v = Value::from_number(n);
+
+      assert!(v.is_number());
+      assert!(unsafe { v.as_number_unchecked() }.is_nan());
+      assert!(!v.is_nil() && !v.is_userdata() && !v.is_int() && !v.is_bool());
+    }
+  }
+}